@@ -0,0 +1,311 @@
+/* analysis.rs
+
+A reaching-definitions dataflow analysis over the statement list: for
+every point in the program, which assignments could still be "live"
+(not yet overwritten) when execution reaches that point.  This is the
+first analysis pass in the project rather than an execution engine --
+it doesn't run or print the program, it answers questions about it,
+like "could `x` still be uninitialized here?"
+*/
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::model::{location_name, Node};
+
+pub type Label = usize;
+
+struct CfgNode {
+    label: Label,
+    /* The variable this node defines, if it's an
+       AssignmentStatement/VarDefinition/ConstDefinition. */
+    def: Option<String>,
+    /* Variables loaded by this node's own expression(s) -- not
+       counting defs/uses belonging to nested statements. */
+    uses: HashSet<String>,
+    successors: Vec<Label>,
+}
+
+struct Builder {
+    nodes: Vec<CfgNode>,
+}
+
+impl Builder {
+    fn new_label(&mut self, def: Option<String>, uses: HashSet<String>) -> Label {
+        let label = self.nodes.len();
+        self.nodes.push(CfgNode { label, def, uses, successors: Vec::new() });
+        label
+    }
+
+    fn add_edge(&mut self, from: Label, to: Label) {
+        self.nodes[from].successors.push(to);
+    }
+
+    /* Builds the CFG for one statement (or a `Pair` chain of them),
+       returning the label it starts with and the labels that should
+       gain an edge to whatever follows. A `None` entry means the
+       statement contributed no CFG nodes at all (an empty `Nil` body). */
+    fn build(&mut self, node: &Node) -> (Option<Label>, Vec<Label>) {
+        use Node::*;
+        match node {
+            Nil => (None, Vec::new()),
+            Pair(first, rest) => {
+                let (first_entry, first_exits) = self.build(first);
+                let (rest_entry, rest_exits) = self.build(rest);
+                match rest_entry {
+                    Some(rest_entry) => {
+                        for &exit in &first_exits {
+                            self.add_edge(exit, rest_entry);
+                        }
+                        (first_entry.or(Some(rest_entry)), rest_exits)
+                    },
+                    None => (first_entry, first_exits),
+                }
+            },
+            Statements(stmts) => {
+                let mut entry = None;
+                let mut exits = Vec::new();
+                for stmt in stmts {
+                    let (stmt_entry, stmt_exits) = self.build(stmt);
+                    if let Some(stmt_entry) = stmt_entry {
+                        for &exit in &exits {
+                            self.add_edge(exit, stmt_entry);
+                        }
+                        entry = entry.or(Some(stmt_entry));
+                        exits = stmt_exits;
+                    }
+                }
+                (entry, exits)
+            },
+            ConstDefinition { name, value, .. } | VarDefinition { name, value, .. } => {
+                let label = self.new_label(Some(name.clone()), used_vars(value));
+                (Some(label), vec![label])
+            },
+            AssignmentStatement { location, expression } => {
+                let name = location_name(location).to_string();
+                let label = self.new_label(Some(name), used_vars(expression));
+                (Some(label), vec![label])
+            },
+            PrintStatement(expr) => {
+                let label = self.new_label(None, used_vars(expr));
+                (Some(label), vec![label])
+            },
+            IfStatement { test, consequence, alternative } => {
+                let label = self.new_label(None, used_vars(test));
+                let (cons_entry, cons_exits) = self.build(consequence);
+                let (alt_entry, alt_exits) = self.build(alternative);
+
+                let mut exits = Vec::new();
+                match cons_entry {
+                    Some(entry) => {
+                        self.add_edge(label, entry);
+                        exits.extend(cons_exits);
+                    },
+                    None => exits.push(label),
+                }
+                match alt_entry {
+                    Some(entry) => {
+                        self.add_edge(label, entry);
+                        exits.extend(alt_exits);
+                    },
+                    None => exits.push(label),
+                }
+
+                (Some(label), exits)
+            },
+            WhileStatement { test, body } => {
+                let label = self.new_label(None, used_vars(test));
+                let (body_entry, body_exits) = self.build(body);
+                if let Some(body_entry) = body_entry {
+                    self.add_edge(label, body_entry);
+                    for &exit in &body_exits {
+                        self.add_edge(exit, label); // back-edge
+                    }
+                }
+                (Some(label), vec![label])
+            },
+            FunctionDefinition { name, .. } => {
+                eprintln!("analysis: function definitions are not modeled in the CFG yet, skipping '{}'", name);
+                (None, Vec::new())
+            },
+            ReturnStatement(expr) => {
+                let label = self.new_label(None, used_vars(expr));
+                (Some(label), vec![label])
+            },
+            _ => panic!("analysis: not a statement: {:?}", node),
+        }
+    }
+}
+
+fn used_vars(node: &Node) -> HashSet<String> {
+    let mut vars = HashSet::new();
+    collect_used_vars(node, &mut vars);
+    vars
+}
+
+fn collect_used_vars(node: &Node, out: &mut HashSet<String>) {
+    use Node::*;
+    match node {
+        BinOp { left, right, .. } => {
+            collect_used_vars(left, out);
+            collect_used_vars(right, out);
+        },
+        UnaryOp { value, .. } => collect_used_vars(value, out),
+        LoadLocation(loc) => {
+            out.insert(location_name(loc).to_string());
+        },
+        Pair(first, rest) => {
+            collect_used_vars(first, out);
+            collect_used_vars(rest, out);
+        },
+        FunctionCall { args, .. } => collect_used_vars(args, out),
+        _ => { },
+    }
+}
+
+fn build_cfg(program: &Node) -> Vec<CfgNode> {
+    let mut builder = Builder { nodes: Vec::new() };
+    builder.build(program);
+    builder.nodes
+}
+
+type DefSet = HashSet<(String, Label)>;
+
+/* `gen[n]` is the single definition `n` introduces (if any); `kill[n]`
+   is every *other* definition of that same variable anywhere in the
+   program, since a fresh assignment makes all earlier ones stop
+   reaching past this point. */
+fn gen_kill(nodes: &[CfgNode]) -> (Vec<DefSet>, Vec<DefSet>) {
+    let mut defs_by_var: HashMap<&str, Vec<Label>> = HashMap::new();
+    for node in nodes {
+        if let Some(name) = &node.def {
+            defs_by_var.entry(name.as_str()).or_default().push(node.label);
+        }
+    }
+
+    let mut gen = vec![HashSet::new(); nodes.len()];
+    let mut kill = vec![HashSet::new(); nodes.len()];
+    for node in nodes {
+        if let Some(name) = &node.def {
+            gen[node.label].insert((name.clone(), node.label));
+            for &other in &defs_by_var[name.as_str()] {
+                if other != node.label {
+                    kill[node.label].insert((name.clone(), other));
+                }
+            }
+        }
+    }
+    (gen, kill)
+}
+
+/* Forward dataflow, solved with a worklist until fixpoint:
+       in[n]  = union of out[p] over predecessors p
+       out[n] = gen[n] U (in[n] - kill[n]) */
+fn solve(nodes: &[CfgNode]) -> (Vec<DefSet>, Vec<DefSet>) {
+    let (gen, kill) = gen_kill(nodes);
+
+    let mut preds: Vec<Vec<Label>> = vec![Vec::new(); nodes.len()];
+    for node in nodes {
+        for &succ in &node.successors {
+            preds[succ].push(node.label);
+        }
+    }
+
+    let mut in_sets: Vec<DefSet> = vec![HashSet::new(); nodes.len()];
+    let mut out_sets: Vec<DefSet> = gen.clone();
+    let mut worklist: VecDeque<Label> = (0..nodes.len()).collect();
+
+    while let Some(n) = worklist.pop_front() {
+        let mut new_in = HashSet::new();
+        for &p in &preds[n] {
+            new_in.extend(out_sets[p].iter().cloned());
+        }
+
+        let mut new_out = gen[n].clone();
+        for def in &new_in {
+            if !kill[n].contains(def) {
+                new_out.insert(def.clone());
+            }
+        }
+
+        let changed = new_out != out_sets[n];
+        in_sets[n] = new_in;
+        out_sets[n] = new_out;
+
+        if changed {
+            for &succ in &nodes[n].successors {
+                worklist.push_back(succ);
+            }
+        }
+    }
+
+    (in_sets, out_sets)
+}
+
+/* The reaching-definitions set at the *end* of each labeled statement,
+   keyed by that statement's own label. */
+pub fn reaching_definitions(program: &Node) -> HashMap<Label, DefSet> {
+    let nodes = build_cfg(program);
+    let (_, out_sets) = solve(&nodes);
+    out_sets.into_iter().enumerate().collect()
+}
+
+/* Flags every `(variable, label)` where the statement at `label`
+   loads `variable` but no definition of it reaches that point -- i.e.
+   it may be used before it's assigned. */
+pub fn flag_uninitialized(program: &Node) -> Vec<(String, Label)> {
+    let nodes = build_cfg(program);
+    let (in_sets, _) = solve(&nodes);
+
+    let mut flagged = Vec::new();
+    for node in &nodes {
+        let defined: HashSet<&str> = in_sets[node.label].iter().map(|(name, _)| name.as_str()).collect();
+        for used in &node.uses {
+            if !defined.contains(used.as_str()) {
+                flagged.push((used.clone(), node.label));
+            }
+        }
+    }
+    flagged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::*;
+
+    #[test]
+    fn flags_a_variable_used_before_any_definition() {
+        let program = Statements(vec![
+            PrintStatement(LoadLocation(NamedLocation("x".to_string()))),
+            VarDefinition("x".to_string(), "int".to_string(), Integer(0)),
+        ]);
+        let flagged = flag_uninitialized(&program);
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].0, "x");
+    }
+
+    #[test]
+    fn does_not_flag_a_variable_used_after_its_definition() {
+        let program = Statements(vec![
+            VarDefinition("x".to_string(), "int".to_string(), Integer(0)),
+            PrintStatement(LoadLocation(NamedLocation("x".to_string()))),
+        ]);
+        assert!(flag_uninitialized(&program).is_empty());
+    }
+
+    /* Reaching-definitions is a "may" analysis: a definition on just
+       one branch of an `if` already counts as reaching the join point,
+       so this is *not* flagged even though the other branch skips it. */
+    #[test]
+    fn does_not_flag_a_variable_defined_on_only_one_branch_of_an_if() {
+        let program = Statements(vec![
+            IfStatement(
+                Integer(1),
+                Statements(vec![VarDefinition("x".to_string(), "int".to_string(), Integer(1))]),
+                Nil(),
+            ),
+            PrintStatement(LoadLocation(NamedLocation("x".to_string()))),
+        ]);
+        assert!(flag_uninitialized(&program).is_empty());
+    }
+}
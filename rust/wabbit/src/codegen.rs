@@ -0,0 +1,289 @@
+/* codegen.rs
+
+Lowers a `Node` tree into a flat list of instructions for a simple
+stack machine, and provides `run` to execute that instruction list.
+This is a second, lower-level way to run a Wabbit program alongside
+the tree-walking `interp` module -- "compiling backwards" down to
+something a dumb executor can just step through.
+*/
+
+use std::collections::HashMap;
+
+use crate::model::{location_name, Node, Operator};
+
+#[derive(Debug, Clone)]
+pub enum Instr {
+    PushInt(i32),
+    PushFloat(f64),
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Neg,
+    CmpLt,
+    CmpLe,
+    CmpGt,
+    CmpGe,
+    CmpEq,
+    CmpNe,
+    Load(String),
+    Store(String),
+    Print,
+    Jump(usize),
+    JumpIfFalse(usize),
+}
+
+pub fn compile(program: &Node) -> Vec<Instr> {
+    let mut code = Vec::new();
+    compile_stmt(program, &mut code);
+    code
+}
+
+fn compile_stmt(node: &Node, code: &mut Vec<Instr>) {
+    use Node::*;
+    match node {
+        Nil => { },
+        Pair(first, rest) => {
+            compile_stmt(first, code);
+            compile_stmt(rest, code);
+        },
+        Statements(stmts) => {
+            for stmt in stmts {
+                compile_stmt(stmt, code);
+            }
+        },
+        ConstDefinition { name, value, .. } => {
+            compile_expr(value, code);
+            code.push(Instr::Store(name.clone()));
+        },
+        VarDefinition { name, dtype, value } => {
+            if let Nil = **value {
+                code.push(if dtype == "float" { Instr::PushFloat(0.0) } else { Instr::PushInt(0) });
+            } else {
+                compile_expr(value, code);
+            }
+            code.push(Instr::Store(name.clone()));
+        },
+        AssignmentStatement { location, expression } => {
+            compile_expr(expression, code);
+            code.push(Instr::Store(location_name(location).to_string()));
+        },
+        PrintStatement(expr) => {
+            compile_expr(expr, code);
+            code.push(Instr::Print);
+        },
+        IfStatement { test, consequence, alternative } => {
+            compile_expr(test, code);
+            let jump_if_false = code.len();
+            code.push(Instr::JumpIfFalse(0)); // patched once we know where the else-branch starts
+            compile_stmt(consequence, code);
+            let jump_over_alt = code.len();
+            code.push(Instr::Jump(0)); // patched once we know where the if-statement ends
+            let alt_start = code.len();
+            compile_stmt(alternative, code);
+            let end = code.len();
+            code[jump_if_false] = Instr::JumpIfFalse(alt_start);
+            code[jump_over_alt] = Instr::Jump(end);
+        },
+        WhileStatement { test, body } => {
+            let loop_start = code.len();
+            compile_expr(test, code);
+            let jump_if_false = code.len();
+            code.push(Instr::JumpIfFalse(0)); // patched once we know where the loop ends
+            compile_stmt(body, code);
+            code.push(Instr::Jump(loop_start));
+            let end = code.len();
+            code[jump_if_false] = Instr::JumpIfFalse(end);
+        },
+        FunctionDefinition { name, .. } => {
+            eprintln!("codegen: function definitions are not compiled yet, skipping '{}'", name);
+        },
+        ReturnStatement(_) => {
+            eprintln!("codegen: 'return' is not supported outside a function call yet, ignoring");
+        },
+        _ => panic!("codegen: not a statement: {:?}", node),
+    }
+}
+
+fn compile_expr(node: &Node, code: &mut Vec<Instr>) {
+    use Node::*;
+    match node {
+        Integer(v) => code.push(Instr::PushInt(*v)),
+        Float(v) => code.push(Instr::PushFloat(*v)),
+        BinOp { op, left, right } => {
+            compile_expr(left, code);
+            compile_expr(right, code);
+            code.push(binop_instr(*op));
+        },
+        UnaryOp { op, value } => {
+            compile_expr(value, code);
+            match op {
+                Operator::MINUS => code.push(Instr::Neg),
+                Operator::PLUS => { },
+                _ => panic!("codegen: '{:?}' is not a unary operator", op),
+            }
+        },
+        LoadLocation(loc) => code.push(Instr::Load(location_name(loc).to_string())),
+        FunctionCall { name, .. } => {
+            eprintln!("codegen: calls to '{}' are not compiled yet, pushing 0", name);
+            code.push(Instr::PushInt(0));
+        },
+        _ => panic!("codegen: not an expression: {:?}", node),
+    }
+}
+
+fn binop_instr(op: Operator) -> Instr {
+    match op {
+        Operator::PLUS => Instr::Add,
+        Operator::MINUS => Instr::Sub,
+        Operator::TIMES => Instr::Mul,
+        Operator::DIVIDE => Instr::Div,
+        Operator::LT => Instr::CmpLt,
+        Operator::LE => Instr::CmpLe,
+        Operator::GT => Instr::CmpGt,
+        Operator::GE => Instr::CmpGe,
+        Operator::EQ => Instr::CmpEq,
+        Operator::NE => Instr::CmpNe,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Int(i32),
+    Float(f64),
+}
+
+pub fn run(code: &[Instr]) {
+    for value in execute(code) {
+        match value {
+            Value::Int(n) => println!("{}", n),
+            Value::Float(f) => println!("{}", f),
+        }
+    }
+}
+
+/* Runs the VM and returns every value a `Print` instruction produced,
+   in order, instead of printing it directly -- this is what `run`
+   prints, and what tests assert against. */
+fn execute(code: &[Instr]) -> Vec<Value> {
+    let mut stack: Vec<Value> = Vec::new();
+    let mut vars: HashMap<String, Value> = HashMap::new();
+    let mut printed = Vec::new();
+    let mut pc = 0;
+
+    while pc < code.len() {
+        match &code[pc] {
+            Instr::PushInt(v) => stack.push(Value::Int(*v)),
+            Instr::PushFloat(v) => stack.push(Value::Float(*v)),
+            Instr::Add => arithmetic(&mut stack, |a, b| a + b, |a, b| a + b),
+            Instr::Sub => arithmetic(&mut stack, |a, b| a - b, |a, b| a - b),
+            Instr::Mul => arithmetic(&mut stack, |a, b| a * b, |a, b| a * b),
+            Instr::Div => arithmetic(&mut stack, |a, b| a / b, |a, b| a / b),
+            Instr::Neg => {
+                let v = pop(&mut stack);
+                stack.push(match v {
+                    Value::Int(n) => Value::Int(-n),
+                    Value::Float(f) => Value::Float(-f),
+                });
+            },
+            Instr::CmpLt => compare(&mut stack, |a, b| a < b, |a, b| a < b),
+            Instr::CmpLe => compare(&mut stack, |a, b| a <= b, |a, b| a <= b),
+            Instr::CmpGt => compare(&mut stack, |a, b| a > b, |a, b| a > b),
+            Instr::CmpGe => compare(&mut stack, |a, b| a >= b, |a, b| a >= b),
+            Instr::CmpEq => compare(&mut stack, |a, b| a == b, |a, b| a == b),
+            Instr::CmpNe => compare(&mut stack, |a, b| a != b, |a, b| a != b),
+            Instr::Load(name) => {
+                let v = *vars.get(name).unwrap_or_else(|| panic!("codegen vm: undefined variable '{}'", name));
+                stack.push(v);
+            },
+            Instr::Store(name) => {
+                let v = pop(&mut stack);
+                vars.insert(name.clone(), v);
+            },
+            Instr::Print => {
+                printed.push(pop(&mut stack));
+            },
+            Instr::Jump(target) => {
+                pc = *target;
+                continue;
+            },
+            Instr::JumpIfFalse(target) => {
+                if !truthy(pop(&mut stack)) {
+                    pc = *target;
+                    continue;
+                }
+            },
+        }
+        pc += 1;
+    }
+
+    printed
+}
+
+fn pop(stack: &mut Vec<Value>) -> Value {
+    stack.pop().expect("codegen vm: stack underflow")
+}
+
+fn truthy(value: Value) -> bool {
+    match value {
+        Value::Int(n) => n != 0,
+        Value::Float(f) => f != 0.0,
+    }
+}
+
+fn arithmetic(stack: &mut Vec<Value>, int_op: impl Fn(i32, i32) -> i32, float_op: impl Fn(f64, f64) -> f64) {
+    let b = pop(stack);
+    let a = pop(stack);
+    stack.push(match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Value::Int(int_op(a, b)),
+        (Value::Float(a), Value::Float(b)) => Value::Float(float_op(a, b)),
+        _ => panic!("codegen vm: cannot combine mismatched int/float operands"),
+    });
+}
+
+fn compare(stack: &mut Vec<Value>, int_op: impl Fn(i32, i32) -> bool, float_op: impl Fn(f64, f64) -> bool) {
+    let b = pop(stack);
+    let a = pop(stack);
+    let result = match (a, b) {
+        (Value::Int(a), Value::Int(b)) => int_op(a, b),
+        (Value::Float(a), Value::Float(b)) => float_op(a, b),
+        _ => panic!("codegen vm: cannot compare mismatched int/float operands"),
+    };
+    stack.push(Value::Int(if result { 1 } else { 0 }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::*;
+
+    #[test]
+    fn compiles_and_runs_a_while_loop() {
+        let program = Statements(vec![
+            VarDefinition("x".to_string(), "int".to_string(), Integer(0)),
+            WhileStatement(
+                BinOp(Operator::LT, LoadLocation(NamedLocation("x".to_string())), Integer(3)),
+                Statements(vec![
+                    PrintStatement(LoadLocation(NamedLocation("x".to_string()))),
+                    AssignmentStatement(
+                        NamedLocation("x".to_string()),
+                        BinOp(Operator::PLUS, LoadLocation(NamedLocation("x".to_string())), Integer(1)),
+                    ),
+                ]),
+            ),
+        ]);
+        let code = compile(&program);
+        assert_eq!(execute(&code), vec![Value::Int(0), Value::Int(1), Value::Int(2)]);
+    }
+
+    #[test]
+    fn if_statement_jumps_to_the_right_branch() {
+        let program = IfStatement(
+            BinOp(Operator::GT, Integer(1), Integer(2)),
+            PrintStatement(Integer(1)),
+            PrintStatement(Integer(2)),
+        );
+        let code = compile(&program);
+        assert_eq!(execute(&code), vec![Value::Int(2)]);
+    }
+}
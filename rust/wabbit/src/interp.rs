@@ -0,0 +1,262 @@
+/* interp.rs
+
+A tree-walking interpreter: instead of turning a `Node` back into
+source text like `to_source`, `interpret` walks it and actually
+carries out what it describes.  Variables live in an `Environment`
+that's just a stack of scopes, pushed for the body of an `if`/`while`
+and popped again once it's done.
+*/
+
+use std::collections::HashMap;
+
+use crate::model::{location_name, Node, Operator};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Value {
+    Int(i32),
+    Float(f64),
+}
+
+struct Binding {
+    value: Value,
+    is_const: bool,
+}
+
+struct Environment {
+    scopes: Vec<HashMap<String, Binding>>,
+}
+
+impl Environment {
+    fn new() -> Self {
+        Environment { scopes: vec![HashMap::new()] }
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn define(&mut self, name: &str, value: Value, is_const: bool) {
+        self.scopes.last_mut().unwrap().insert(name.to_string(), Binding { value, is_const });
+    }
+
+    fn lookup(&self, name: &str) -> Value {
+        for scope in self.scopes.iter().rev() {
+            if let Some(binding) = scope.get(name) {
+                return binding.value;
+            }
+        }
+        panic!("interp: undefined variable '{}'", name);
+    }
+
+    fn assign(&mut self, name: &str, value: Value) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(binding) = scope.get_mut(name) {
+                if binding.is_const {
+                    panic!("interp: cannot assign to const '{}'", name);
+                }
+                binding.value = value;
+                return;
+            }
+        }
+        panic!("interp: undefined variable '{}'", name);
+    }
+}
+
+pub fn interpret(program: &Node) {
+    let mut env = Environment::new();
+    exec(program, &mut env);
+}
+
+fn exec(node: &Node, env: &mut Environment) {
+    use Node::*;
+    match node {
+        Nil => { },
+        Pair(first, rest) => {
+            exec(first, env);
+            exec(rest, env);
+        },
+        Statements(stmts) => {
+            for stmt in stmts {
+                exec(stmt, env);
+            }
+        },
+        ConstDefinition { name, value, .. } => {
+            let v = eval(value, env);
+            env.define(name, v, true);
+        },
+        VarDefinition { name, dtype, value } => {
+            let v = match &**value {
+                Nil => default_value(dtype),
+                value => eval(value, env),
+            };
+            env.define(name, v, false);
+        },
+        AssignmentStatement { location, expression } => {
+            let v = eval(expression, env);
+            env.assign(location_name(location), v);
+        },
+        PrintStatement(expr) => {
+            match eval(expr, env) {
+                Value::Int(n) => println!("{}", n),
+                Value::Float(f) => println!("{}", f),
+            }
+        },
+        IfStatement { test, consequence, alternative } => {
+            env.push_scope();
+            if eval_test(test, env) {
+                exec(consequence, env);
+            } else {
+                exec(alternative, env);
+            }
+            env.pop_scope();
+        },
+        WhileStatement { test, body } => {
+            while eval_test(test, env) {
+                env.push_scope();
+                exec(body, env);
+                env.pop_scope();
+            }
+        },
+        FunctionDefinition { name, .. } => {
+            eprintln!("interp: function definitions are not executed yet, skipping '{}'", name);
+        },
+        ReturnStatement(_) => {
+            eprintln!("interp: 'return' is not supported outside a function call yet, ignoring");
+        },
+        _ => panic!("interp: not a statement: {:?}", node),
+    }
+}
+
+fn default_value(dtype: &str) -> Value {
+    if dtype == "float" {
+        Value::Float(0.0)
+    } else {
+        Value::Int(0)
+    }
+}
+
+fn eval(node: &Node, env: &Environment) -> Value {
+    use Node::*;
+    match node {
+        Integer(v) => Value::Int(*v),
+        Float(v) => Value::Float(*v),
+        BinOp { op, left, right } => arithmetic(*op, eval(left, env), eval(right, env)),
+        UnaryOp { op, value } => match (op, eval(value, env)) {
+            (Operator::MINUS, Value::Int(n)) => Value::Int(-n),
+            (Operator::MINUS, Value::Float(f)) => Value::Float(-f),
+            (Operator::PLUS, v) => v,
+            (op, _) => panic!("interp: invalid unary operator {:?}", op),
+        },
+        LoadLocation(loc) => env.lookup(location_name(loc)),
+        FunctionCall { name, .. } => {
+            eprintln!("interp: calls to '{}' are not supported yet, evaluating to 0", name);
+            Value::Int(0)
+        },
+        _ => panic!("interp: not an expression: {:?}", node),
+    }
+}
+
+/* `if`/`while` tests are always a comparison -- Wabbit has no
+   standalone boolean type, so the comparison operators are handled
+   separately from the arithmetic ones in `eval`. */
+fn eval_test(node: &Node, env: &Environment) -> bool {
+    match node {
+        Node::BinOp { op, left, right } if op.is_comparison() => {
+            compare(*op, eval(left, env), eval(right, env))
+        },
+        _ => panic!("interp: expected a comparison expression, got {:?}", node),
+    }
+}
+
+fn arithmetic(op: Operator, left: Value, right: Value) -> Value {
+    match (left, right) {
+        (Value::Int(a), Value::Int(b)) => Value::Int(match op {
+            Operator::PLUS => a + b,
+            Operator::MINUS => a - b,
+            Operator::TIMES => a * b,
+            Operator::DIVIDE => a / b,
+            _ => panic!("interp: '{:?}' is not an arithmetic operator", op),
+        }),
+        (Value::Float(a), Value::Float(b)) => Value::Float(match op {
+            Operator::PLUS => a + b,
+            Operator::MINUS => a - b,
+            Operator::TIMES => a * b,
+            Operator::DIVIDE => a / b,
+            _ => panic!("interp: '{:?}' is not an arithmetic operator", op),
+        }),
+        _ => panic!("interp: cannot combine mismatched int/float operands"),
+    }
+}
+
+fn compare(op: Operator, left: Value, right: Value) -> bool {
+    match (left, right) {
+        (Value::Int(a), Value::Int(b)) => match op {
+            Operator::LT => a < b,
+            Operator::LE => a <= b,
+            Operator::GT => a > b,
+            Operator::GE => a >= b,
+            Operator::EQ => a == b,
+            Operator::NE => a != b,
+            _ => panic!("interp: '{:?}' is not a comparison operator", op),
+        },
+        (Value::Float(a), Value::Float(b)) => match op {
+            Operator::LT => a < b,
+            Operator::LE => a <= b,
+            Operator::GT => a > b,
+            Operator::GE => a >= b,
+            Operator::EQ => a == b,
+            Operator::NE => a != b,
+            _ => panic!("interp: '{:?}' is not a comparison operator", op),
+        },
+        _ => panic!("interp: cannot compare mismatched int/float operands"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::*;
+
+    #[test]
+    fn eval_loads_a_defined_variable() {
+        let mut env = Environment::new();
+        env.define("x", Value::Int(5), false);
+        let expr = BinOp(Operator::PLUS, LoadLocation(NamedLocation("x".to_string())), Integer(3));
+        match eval(&expr, &env) {
+            Value::Int(n) => assert_eq!(n, 8),
+            other => panic!("expected Value::Int, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot assign to const")]
+    fn assigning_to_a_const_panics() {
+        let mut env = Environment::new();
+        env.define("pi", Value::Float(3.14), true);
+        env.assign("pi", Value::Float(0.0));
+    }
+
+    #[test]
+    fn while_loop_leaves_the_expected_final_value() {
+        let mut env = Environment::new();
+        let program = Statements(vec![
+            VarDefinition("x".to_string(), "int".to_string(), Integer(0)),
+            WhileStatement(
+                BinOp(Operator::LT, LoadLocation(NamedLocation("x".to_string())), Integer(3)),
+                Statements(vec![AssignmentStatement(
+                    NamedLocation("x".to_string()),
+                    BinOp(Operator::PLUS, LoadLocation(NamedLocation("x".to_string())), Integer(1)),
+                )]),
+            ),
+        ]);
+        exec(&program, &mut env);
+        match env.lookup("x") {
+            Value::Int(n) => assert_eq!(n, 3),
+            other => panic!("expected Value::Int, got {:?}", other),
+        }
+    }
+}
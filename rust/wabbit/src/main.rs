@@ -1,5 +1,69 @@
 mod model;
+mod tokenizer;
+mod parser;
+mod interp;
+mod typecheck;
+mod codegen;
+mod analysis;
 use model::*;
+use interp::interpret;
+
+fn compile_and_run(program: &Node) {
+    let code = codegen::compile(program);
+    codegen::run(&code);
+}
+
+fn report_types(program: &Node) {
+    let errors = typecheck::check(program);
+    if errors.is_empty() {
+        println!("(typechecks ok)");
+    } else {
+        for error in &errors {
+            println!("type error: {}", error.0);
+        }
+    }
+}
+
+fn report_uninitialized(program: &Node) {
+    let flagged = analysis::flag_uninitialized(program);
+    if flagged.is_empty() {
+        println!("(no possibly-uninitialized uses)");
+    } else {
+        for (name, label) in &flagged {
+            println!("warning: '{}' may be used before assignment at label {}", name, label);
+        }
+    }
+}
+
+/* Prints the reaching-definitions set at the end of every labeled
+   statement, the same information `report_uninitialized` is built on
+   top of. */
+fn report_reaching_definitions(program: &Node) {
+    let reaching = analysis::reaching_definitions(program);
+    let mut labels: Vec<_> = reaching.keys().copied().collect();
+    labels.sort();
+    for label in labels {
+        let mut defs: Vec<String> = reaching[&label].iter().map(|(name, def)| format!("{}@{}", name, def)).collect();
+        defs.sort();
+        println!("reaching definitions at {}: {{{}}}", label, defs.join(", "));
+    }
+}
+
+/* Renders a model back to source and parses it again, as a sanity check
+   that `parser::parse` reconstructs the same tree `to_source` printed. */
+fn report_parse_round_trip(program: &Node) {
+    let src = to_source_string(program);
+    match parser::parse(&src) {
+        Ok(reparsed) => {
+            if *reparsed == *program {
+                println!("(parses back to an identical tree)");
+            } else {
+                println!("parse round-trip produced a different tree");
+            }
+        },
+        Err(parser::ParseError(message)) => println!("parse error: {}", message),
+    }
+}
 
 /* 
 expr_source = "2 + 3 * 4;"
@@ -47,7 +111,7 @@ fn model1() -> NodeType {
 			  Integer(3)),
 		    Integer(4)));
 
-    Pair(s1, Pair(s2, Pair(s3, Pair(s4, Nil()))))
+    Statements(vec![s1, s2, s3, s4])
 }
 
 /*
@@ -67,7 +131,7 @@ fn model2() -> NodeType {
 				   Float(2.0),
 				   LoadLocation(NamedLocation("pi".to_string()))));
     let s4 = PrintStatement(LoadLocation(NamedLocation("tau".to_string())));
-    Pair(s1, Pair(s2, Pair(s3, Pair(s4, Nil()))))    
+    Statements(vec![s1, s2, s3, s4])
 }
 
 
@@ -86,13 +150,13 @@ source3 = '''
 fn model3() -> NodeType {
     let s1 = VarDefinition("a".to_string(), "int".to_string(), Integer(2));
     let s2 = VarDefinition("b".to_string(), "int".to_string(), Integer(3));
-    let s3 = Pair(PrintStatement(LoadLocation(NamedLocation("a".to_string()))), Nil());
-    let s4 = Pair(PrintStatement(LoadLocation(NamedLocation("b".to_string()))), Nil());
+    let s3 = Statements(vec![PrintStatement(LoadLocation(NamedLocation("a".to_string())))]);
+    let s4 = Statements(vec![PrintStatement(LoadLocation(NamedLocation("b".to_string())))]);
     let s5 = IfStatement(BinOp(Operator::LT,
 			       LoadLocation(NamedLocation("a".to_string())),
 			       LoadLocation(NamedLocation("b".to_string()))),
 			 s3, s4);
-    Pair(s1, Pair(s2, Pair(s5, Nil())))
+    Statements(vec![s1, s2, s5])
 }
 
 /*
@@ -127,9 +191,48 @@ fn model4() -> NodeType {
     let s4 = WhileStatement(BinOp(Operator::LT,
 				  LoadLocation(NamedLocation("x".to_string())),
 				  LoadLocation(NamedLocation("n".to_string()))),
-			    Pair(b1, Pair(b2, Pair(b3, Nil()))));
+			    Statements(vec![b1, b2, b3]));
+
+    Statements(vec![s1, s2, s3, s4])
+}
+
+/*
+source5 = '''
+    func factorial(n int) int {
+        if n < 2 {
+            return 1;
+        } else {
+            return n * factorial(n - 1);
+        }
+    }
+    print factorial(5);
+'''
+ */
+
+fn model5() -> NodeType {
+    let base_case = Statements(vec![ReturnStatement(Integer(1))]);
+    let recursive_case = Statements(vec![
+	ReturnStatement(
+	    BinOp(Operator::TIMES,
+		  LoadLocation(NamedLocation("n".to_string())),
+		  FunctionCall("factorial".to_string(),
+			       Pair(BinOp(Operator::MINUS,
+					  LoadLocation(NamedLocation("n".to_string())),
+					  Integer(1)),
+				    Nil())))),
+    ]);
+    let body = Statements(vec![
+	IfStatement(BinOp(Operator::LT, LoadLocation(NamedLocation("n".to_string())), Integer(2)),
+		    base_case,
+		    recursive_case),
+    ]);
+    let s1 = FunctionDefinition("factorial".to_string(),
+				vec![("n".to_string(), "int".to_string())],
+				"int".to_string(),
+				body);
+    let s2 = PrintStatement(FunctionCall("factorial".to_string(), Pair(Integer(5), Nil())));
 
-    Pair(s1, Pair(s2, Pair(s3, Pair(s4, Nil()))))
+    Statements(vec![s1, s2])
 }
 
 
@@ -142,17 +245,59 @@ fn main() {
     let m1 = model1();
     println!("\n----- MODEL 1");
     to_source(&m1);
+    report_types(&m1);
+    report_uninitialized(&m1);
+    report_reaching_definitions(&m1);
+    report_parse_round_trip(&m1);
+    println!("\n----- MODEL 1 (running)");
+    interpret(&m1);
+    println!("\n----- MODEL 1 (constant-folded)");
+    to_source(&fold_constants(*model1()));
+    println!("\n----- MODEL 1 (compiled)");
+    compile_and_run(&m1);
 
     let m2 = model2();
     println!("\n----- MODEL 2");
     to_source(&m2);
+    report_types(&m2);
+    report_uninitialized(&m2);
+    report_reaching_definitions(&m2);
+    report_parse_round_trip(&m2);
+    println!("\n----- MODEL 2 (running)");
+    interpret(&m2);
 
     let m3 = model3();
     println!("\n----- MODEL 3");
     to_source(&m3);
+    report_types(&m3);
+    report_uninitialized(&m3);
+    report_reaching_definitions(&m3);
+    report_parse_round_trip(&m3);
+    println!("\n----- MODEL 3 (running)");
+    interpret(&m3);
+    println!("\n----- MODEL 3 (compiled)");
+    compile_and_run(&m3);
 
     let m4 = model4();
     println!("\n----- MODEL 4");
     to_source(&m4);
+    report_types(&m4);
+    report_uninitialized(&m4);
+    report_reaching_definitions(&m4);
+    report_parse_round_trip(&m4);
+    println!("\n----- MODEL 4 (running)");
+    interpret(&m4);
+    println!("\n----- MODEL 4 (compiled)");
+    compile_and_run(&m4);
 
+    let m5 = model5();
+    println!("\n----- MODEL 5");
+    to_source(&m5);
+    report_types(&m5);
+    report_uninitialized(&m5);
+    report_reaching_definitions(&m5);
+    // interpret/compile_and_run aren't run here: neither pass supports
+    // function calls yet, and faking a return value (e.g. printing 0
+    // for `factorial(5)`) would look like a real answer instead of
+    // flagging the feature as unsupported.
 }
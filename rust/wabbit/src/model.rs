@@ -52,7 +52,7 @@ if you want to go in a different direction with it.
 #![allow(non_snake_case)]
 
 /* Symbolic representation of all valid operators */
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Operator {
     PLUS,
     MINUS,
@@ -66,8 +66,21 @@ pub enum Operator {
     NE,
 }
 
+impl Operator {
+    /* LT/LE/GT/GE/EQ/NE compare two values and yield a bool; the rest
+       combine two values of the same type into another one of that
+       type.  Several passes (the interpreter, the type-checker,
+       constant folding) need to tell the two groups apart. */
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Operator::LT | Operator::LE | Operator::GT | Operator::GE | Operator::EQ | Operator::NE
+        )
+    }
+}
+
 /* Enum that's used to represent parse tree nodes */
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Node {
     Nil,	 
     Integer(i32),
@@ -108,6 +121,18 @@ pub enum Node {
     },
     NamedLocation(String),
     Pair(Box<Node>, Box<Node>),
+    Statements(Vec<Node>),
+    FunctionDefinition {
+	name: String,
+	params: Vec<(String, String)>,
+	ret_type: String,
+	body: Box<Node>,
+    },
+    FunctionCall {
+	name: String,
+	args: Box<Node>,
+    },
+    ReturnStatement(Box<Node>),
 }
 
 pub type NodeType = Box<Node>;
@@ -164,98 +189,351 @@ pub fn Pair(stmt1: NodeType, stmt2: NodeType) -> NodeType {
     Box::new(Node::Pair(stmt1, stmt2))
 }
 
+/* A block of statements held directly as a `Vec`, instead of chained
+   through nested `Pair`s.  `Pair`/`Nil` still work everywhere they did
+   before; this is just a flatter way to build and traverse a block. */
+pub fn Statements(stmts: Vec<NodeType>) -> NodeType {
+    Box::new(Node::Statements(stmts.into_iter().map(|stmt| *stmt).collect()))
+}
+
+pub fn FunctionDefinition(name: String, params: Vec<(String, String)>, ret_type: String, body: NodeType) -> NodeType {
+    Box::new(Node::FunctionDefinition { name, params, ret_type, body })
+}
+
+pub fn FunctionCall(name: String, args: NodeType) -> NodeType {
+    Box::new(Node::FunctionCall { name, args })
+}
+
+pub fn ReturnStatement(value: NodeType) -> NodeType {
+    Box::new(Node::ReturnStatement(value))
+}
+
 pub fn Nil() -> NodeType {
     Box::new(Node::Nil)
 }
 
+/* Extracts the variable name out of a location node -- `NamedLocation`
+   is the only kind of location the language has so far.  Shared by
+   the interpreter and the type-checker, which both need to turn a
+   location back into the name it binds. */
+pub fn location_name(node: &Node) -> &str {
+    match node {
+        Node::NamedLocation(name) => name,
+        _ => panic!("not a location: {:?}", node),
+    }
+}
+
+/* ------ Constant folding: simplify an expression bottom-up, replacing
+   any operation whose operands are already literals with the literal
+   result.  Nodes with a non-literal child are left alone (their
+   children are still folded).  Integer division is never folded away
+   when the divisor is zero, so the error still surfaces at runtime
+   instead of panicking the compiler itself. */
+
+pub fn fold_constants(node: Node) -> NodeType {
+    match node {
+        Node::BinOp { op, left, right } => {
+            fold_binop(op, fold_constants(*left), fold_constants(*right))
+        },
+        Node::UnaryOp { op, value } => fold_unaryop(op, fold_constants(*value)),
+        Node::PrintStatement(expr) => PrintStatement(fold_constants(*expr)),
+        Node::AssignmentStatement { location, expression } => {
+            AssignmentStatement(fold_constants(*location), fold_constants(*expression))
+        },
+        Node::LoadLocation(loc) => LoadLocation(fold_constants(*loc)),
+        Node::ConstDefinition { name, dtype, value } => {
+            ConstDefinition(name, dtype, fold_constants(*value))
+        },
+        Node::VarDefinition { name, dtype, value } => {
+            VarDefinition(name, dtype, fold_constants(*value))
+        },
+        Node::IfStatement { test, consequence, alternative } => {
+            IfStatement(fold_constants(*test), fold_constants(*consequence), fold_constants(*alternative))
+        },
+        Node::WhileStatement { test, body } => {
+            WhileStatement(fold_constants(*test), fold_constants(*body))
+        },
+        Node::Pair(first, rest) => Pair(fold_constants(*first), fold_constants(*rest)),
+        Node::Statements(stmts) => {
+            Statements(stmts.into_iter().map(fold_constants).collect())
+        },
+        Node::FunctionDefinition { name, params, ret_type, body } => {
+            FunctionDefinition(name, params, ret_type, fold_constants(*body))
+        },
+        Node::FunctionCall { name, args } => FunctionCall(name, fold_constants(*args)),
+        Node::ReturnStatement(value) => ReturnStatement(fold_constants(*value)),
+        other => Box::new(other),
+    }
+}
+
+fn fold_binop(op: Operator, left: NodeType, right: NodeType) -> NodeType {
+    match (&*left, &*right) {
+        (Node::Integer(a), Node::Integer(b)) => {
+            if op.is_comparison() {
+                Integer(bool_to_int(compare_ints(op, *a, *b)))
+            } else if matches!(op, Operator::DIVIDE) && *b == 0 {
+                BinOp(op, left, right)
+            } else {
+                Integer(arith_ints(op, *a, *b))
+            }
+        },
+        (Node::Float(a), Node::Float(b)) => {
+            if op.is_comparison() {
+                Integer(bool_to_int(compare_floats(op, *a, *b)))
+            } else {
+                Float(arith_floats(op, *a, *b))
+            }
+        },
+        _ => BinOp(op, left, right),
+    }
+}
+
+fn fold_unaryop(op: Operator, value: NodeType) -> NodeType {
+    match (op, &*value) {
+        (Operator::MINUS, Node::Integer(n)) => Integer(-n),
+        (Operator::MINUS, Node::Float(f)) => Float(-f),
+        (Operator::PLUS, Node::Integer(n)) => Integer(*n),
+        (Operator::PLUS, Node::Float(f)) => Float(*f),
+        _ => UnaryOp(op, value),
+    }
+}
+
+fn bool_to_int(value: bool) -> i32 {
+    if value { 1 } else { 0 }
+}
+
+fn arith_ints(op: Operator, a: i32, b: i32) -> i32 {
+    match op {
+        Operator::PLUS => a + b,
+        Operator::MINUS => a - b,
+        Operator::TIMES => a * b,
+        Operator::DIVIDE => a / b,
+        _ => unreachable!("{:?} is not an arithmetic operator", op),
+    }
+}
+
+fn arith_floats(op: Operator, a: f64, b: f64) -> f64 {
+    match op {
+        Operator::PLUS => a + b,
+        Operator::MINUS => a - b,
+        Operator::TIMES => a * b,
+        Operator::DIVIDE => a / b,
+        _ => unreachable!("{:?} is not an arithmetic operator", op),
+    }
+}
+
+fn compare_ints(op: Operator, a: i32, b: i32) -> bool {
+    match op {
+        Operator::LT => a < b,
+        Operator::LE => a <= b,
+        Operator::GT => a > b,
+        Operator::GE => a >= b,
+        Operator::EQ => a == b,
+        Operator::NE => a != b,
+        _ => unreachable!("{:?} is not a comparison operator", op),
+    }
+}
+
+fn compare_floats(op: Operator, a: f64, b: f64) -> bool {
+    match op {
+        Operator::LT => a < b,
+        Operator::LE => a <= b,
+        Operator::GT => a > b,
+        Operator::GE => a >= b,
+        Operator::EQ => a == b,
+        Operator::NE => a != b,
+        _ => unreachable!("{:?} is not a comparison operator", op),
+    }
+}
+
 /* ------ Debugging function to convert a model into source code (for easier viewing) */
 
 pub fn to_source(node: &Node) -> () {
+    print!("{}", to_source_string(node));
+}
+
+/* Same traversal as `to_source`, but built up into a `String` instead of
+   printed directly.  This is what lets the parser's round-trip tests
+   compare a model against the source text it renders to. */
+pub fn to_source_string(node: &Node) -> String {
+    let mut buf = String::new();
+    write_source(node, &mut buf);
+    buf
+}
+
+fn write_source(node: &Node, buf: &mut String) {
+    use std::fmt::Write;
     use Node::*;
     match node {
 	Integer(val) => {
-            print!("{}", val);
+            write!(buf, "{}", val).unwrap();
 	},
 	Float(val) => {
-            print!("{}", val);
+            // Keep the decimal point even on whole numbers (`2` vs
+            // `2.0`) so re-tokenizing the source can't mistake a
+            // float literal for an integer one.
+            if val.fract() == 0.0 {
+                write!(buf, "{:.1}", val).unwrap();
+            } else {
+                write!(buf, "{}", val).unwrap();
+            }
 	},
 	BinOp { op, left, right } => {
-            to_source(left);
-    	    match op {
-		Operator::PLUS => print!(" + "),
-   		Operator::MINUS => print!(" - "),
-		Operator::TIMES => print!(" * "),
-		Operator::DIVIDE => print!(" / "),
-		Operator::LT => print!(" < "),
-		Operator::LE => print!(" <= "),
-		Operator::GT => print!(" > "),
-		Operator::GE => print!(" <= "),
-		Operator::EQ => print!(" == "),
-		Operator::NE => print!(" != "),		
-	    }
-	    to_source(right);
+            write_source(left, buf);
+    	    buf.push_str(match op {
+		Operator::PLUS => " + ",
+   		Operator::MINUS => " - ",
+		Operator::TIMES => " * ",
+		Operator::DIVIDE => " / ",
+		Operator::LT => " < ",
+		Operator::LE => " <= ",
+		Operator::GT => " > ",
+		Operator::GE => " >= ",
+		Operator::EQ => " == ",
+		Operator::NE => " != ",
+	    });
+	    write_source(right, buf);
 	},
 	UnaryOp { op, value } => {
-            match op {
-		Operator::PLUS => print!("+"),
-		Operator::MINUS => print!("-"),
-		_ => print!(""),
-            }
-            to_source(value);
+            buf.push_str(match op {
+		Operator::PLUS => "+",
+		Operator::MINUS => "-",
+		_ => "",
+            });
+            write_source(value, buf);
 	},
 	PrintStatement(val) => {
-            print!("print ");
-	    to_source(val);
-	    print!(";\n");
+            buf.push_str("print ");
+	    write_source(val, buf);
+	    buf.push_str(";\n");
 	},
 	AssignmentStatement { location, expression } => {
-	    to_source(location);
-	    print!(" = ");
-	    to_source(expression);
-	    print!(";\n");
+	    write_source(location, buf);
+	    buf.push_str(" = ");
+	    write_source(expression, buf);
+	    buf.push_str(";\n");
 	},
 	VarDefinition { name, dtype, value } => {
-	    print!("var {0} {1}", name, dtype);
+	    write!(buf, "var {0} {1}", name, dtype).unwrap();
 	    if let Nil = **value { } else {
-		print!(" = ");
-		to_source(value);
+		buf.push_str(" = ");
+		write_source(value, buf);
 	    }
-	    print!(";\n");
+	    buf.push_str(";\n");
 	},
 	ConstDefinition { name, dtype, value } => {
-	    print!("const {0} {1} = ", name, dtype);
-	    to_source(value);
-	    print!(";\n");
+	    write!(buf, "const {0} {1} = ", name, dtype).unwrap();
+	    write_source(value, buf);
+	    buf.push_str(";\n");
 	},
 	NamedLocation(name) => {
-	    print!("{}", name);
+	    buf.push_str(name);
 	},
 	LoadLocation(loc) => {
-	    to_source(loc);
+	    write_source(loc, buf);
 	},
 	IfStatement { test, consequence, alternative } => {
-	    print!("if ");
-	    to_source(test);
-	    print!("{}", " {\n");
-	    to_source(consequence);
-	    print!("{}", "} else {\n");
-	    to_source(alternative);
-	    print!("{}", "}\n");
+	    buf.push_str("if ");
+	    write_source(test, buf);
+	    buf.push_str(" {\n");
+	    write_source(consequence, buf);
+	    buf.push_str("} else {\n");
+	    write_source(alternative, buf);
+	    buf.push_str("}\n");
 	},
 	WhileStatement { test, body } => {
-	    print!("while ");
-	    to_source(test);
-	    print!("{}", " {\n");
-	    to_source(body);
-	    print!("{}", "}\n");
+	    buf.push_str("while ");
+	    write_source(test, buf);
+	    buf.push_str(" {\n");
+	    write_source(body, buf);
+	    buf.push_str("}\n");
 	},
 	Pair(node1, node2) => {
-	    to_source(node1);
-	    to_source(node2);
+	    write_source(node1, buf);
+	    write_source(node2, buf);
+	},
+	Statements(stmts) => {
+	    for stmt in stmts {
+		write_source(stmt, buf);
+	    }
+	},
+	FunctionDefinition { name, params, ret_type, body } => {
+	    write!(buf, "func {}(", name).unwrap();
+	    for (i, (pname, ptype)) in params.iter().enumerate() {
+		if i > 0 {
+		    buf.push_str(", ");
+		}
+		write!(buf, "{} {}", pname, ptype).unwrap();
+	    }
+	    write!(buf, ") {}", ret_type).unwrap();
+	    buf.push_str(" {\n");
+	    write_source(body, buf);
+	    buf.push_str("}\n");
+	},
+	FunctionCall { name, args } => {
+	    write!(buf, "{}(", name).unwrap();
+	    write_args(args, buf);
+	    buf.push(')');
+	},
+	ReturnStatement(value) => {
+	    buf.push_str("return ");
+	    write_source(value, buf);
+	    buf.push_str(";\n");
 	},
 	Nil => { },
 	_ => {
-            print!("{:?}", node);
+            write!(buf, "{:?}", node).unwrap();
 	}
     }
 }
+
+/* Call arguments are a `Pair`/`Nil` list too, but rendered comma-separated
+   on one line instead of as a statement block. */
+fn write_args(node: &Node, buf: &mut String) {
+    match node {
+	Node::Nil => { },
+	Node::Pair(first, rest) => {
+	    write_source(first, buf);
+	    if let Node::Pair(..) = **rest {
+		buf.push_str(", ");
+	    }
+	    write_args(rest, buf);
+	},
+	other => write_source(other, buf),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_nested_arithmetic_to_a_single_integer() {
+        let expr = BinOp(Operator::PLUS,
+                          Integer(2),
+                          BinOp(Operator::TIMES, Integer(3), UnaryOp(Operator::MINUS, Integer(4))));
+        assert_eq!(fold_constants(*expr), Integer(-10));
+    }
+
+    #[test]
+    fn leaves_division_by_zero_unfolded() {
+        let expr = BinOp(Operator::DIVIDE, Integer(1), Integer(0));
+        let expected = BinOp(Operator::DIVIDE, Integer(1), Integer(0));
+        assert_eq!(fold_constants(*expr), expected);
+    }
+
+    #[test]
+    fn folds_constants_nested_inside_a_function_call() {
+        let call = FunctionCall("f".to_string(), Pair(BinOp(Operator::PLUS, Integer(2), Integer(3)), Nil()));
+        assert_eq!(fold_constants(*call), FunctionCall("f".to_string(), Pair(Integer(5), Nil())));
+    }
+
+    #[test]
+    fn folds_constants_inside_a_function_body() {
+        let def = FunctionDefinition("f".to_string(), vec![], "int".to_string(),
+                                      Statements(vec![ReturnStatement(BinOp(Operator::TIMES, Integer(2), Integer(3)))]));
+        assert_eq!(fold_constants(*def),
+                   FunctionDefinition("f".to_string(), vec![], "int".to_string(),
+                                      Statements(vec![ReturnStatement(Integer(6))])));
+    }
+}
@@ -0,0 +1,241 @@
+/* parser.rs
+
+A recursive-descent parser that rebuilds a `Node` tree (see
+`model.rs`) from the token stream produced by `tokenizer`.  Statements
+are parsed by dispatching on the leading keyword and collected into a
+`Statements` block.  Expressions use Pratt / precedence-climbing: each
+binary operator has a binding power, and `parse_expression` only
+consumes an operator when it binds at least as tightly as the caller
+requires.
+*/
+
+use crate::model::*;
+use crate::tokenizer::{tokenize, Token};
+
+#[derive(Debug)]
+pub struct ParseError(pub String);
+
+pub fn parse(src: &str) -> Result<NodeType, ParseError> {
+    let mut parser = Parser { tokens: tokenize(src), pos: 0 };
+    let mut stmts = Vec::new();
+    while *parser.peek() != Token::Eof {
+        stmts.push(parser.parse_statement()?);
+    }
+    Ok(Statements(stmts))
+}
+
+/* Parses a single expression with no surrounding statement.  Most
+   Wabbit source is a statement list, but `model0` is a bare
+   expression, so tests that round-trip it need a way in that skips
+   straight to `parse_expression`. */
+#[allow(dead_code)]
+pub(crate) fn parse_expr(src: &str) -> Result<NodeType, ParseError> {
+    let mut parser = Parser { tokens: tokenize(src), pos: 0 };
+    parser.parse_expression(0)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn advance(&mut self) -> Token {
+        let tok = self.tokens[self.pos].clone();
+        self.pos += 1;
+        tok
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), ParseError> {
+        if self.peek() == expected {
+            self.advance();
+            Ok(())
+        } else {
+            Err(ParseError(format!("expected {:?}, got {:?}", expected, self.peek())))
+        }
+    }
+
+    fn parse_name(&mut self) -> Result<String, ParseError> {
+        match self.advance() {
+            Token::Name(name) => Ok(name),
+            tok => Err(ParseError(format!("expected a name, got {:?}", tok))),
+        }
+    }
+
+    /* `var`/`const` carry an optional type annotation between the
+       name and the `=`/`;` -- e.g. `var tau float;` vs `const pi = 3.14159;` */
+    fn parse_opt_dtype(&mut self) -> Result<String, ParseError> {
+        if let Token::Name(dtype) = self.peek().clone() {
+            self.advance();
+            Ok(dtype)
+        } else {
+            Ok("".to_string())
+        }
+    }
+
+    fn parse_block(&mut self) -> Result<NodeType, ParseError> {
+        self.expect(&Token::LBrace)?;
+        let mut stmts = Vec::new();
+        while *self.peek() != Token::RBrace {
+            stmts.push(self.parse_statement()?);
+        }
+        self.advance();
+        Ok(Statements(stmts))
+    }
+
+    fn parse_statement(&mut self) -> Result<NodeType, ParseError> {
+        match self.peek().clone() {
+            Token::Print => {
+                self.advance();
+                let value = self.parse_expression(0)?;
+                self.expect(&Token::Semi)?;
+                Ok(PrintStatement(value))
+            },
+            Token::Const => {
+                self.advance();
+                let name = self.parse_name()?;
+                let dtype = self.parse_opt_dtype()?;
+                self.expect(&Token::Assign)?;
+                let value = self.parse_expression(0)?;
+                self.expect(&Token::Semi)?;
+                Ok(ConstDefinition(name, dtype, value))
+            },
+            Token::Var => {
+                self.advance();
+                let name = self.parse_name()?;
+                let dtype = self.parse_opt_dtype()?;
+                let value = if *self.peek() == Token::Assign {
+                    self.advance();
+                    self.parse_expression(0)?
+                } else {
+                    Nil()
+                };
+                self.expect(&Token::Semi)?;
+                Ok(VarDefinition(name, dtype, value))
+            },
+            Token::If => {
+                self.advance();
+                let test = self.parse_expression(0)?;
+                let consequence = self.parse_block()?;
+                let alternative = if *self.peek() == Token::Else {
+                    self.advance();
+                    self.parse_block()?
+                } else {
+                    Nil()
+                };
+                Ok(IfStatement(test, consequence, alternative))
+            },
+            Token::While => {
+                self.advance();
+                let test = self.parse_expression(0)?;
+                let body = self.parse_block()?;
+                Ok(WhileStatement(test, body))
+            },
+            Token::Name(name) => {
+                self.advance();
+                self.expect(&Token::Assign)?;
+                let value = self.parse_expression(0)?;
+                self.expect(&Token::Semi)?;
+                Ok(AssignmentStatement(NamedLocation(name), value))
+            },
+            tok => Err(ParseError(format!("unexpected token at start of statement: {:?}", tok))),
+        }
+    }
+
+    fn binding_power(op: Operator) -> u8 {
+        match op {
+            Operator::LT | Operator::LE | Operator::GT | Operator::GE
+                | Operator::EQ | Operator::NE => 1,
+            Operator::PLUS | Operator::MINUS => 2,
+            Operator::TIMES | Operator::DIVIDE => 3,
+        }
+    }
+
+    /* Precedence climbing: keep folding in infix operators as long as
+       their binding power is at least `min_bp`.  The right operand of
+       each operator is parsed with `bp + 1` so that e.g. `+` is
+       left-associative instead of swallowing a trailing `+` at the
+       same precedence. */
+    fn parse_expression(&mut self, min_bp: u8) -> Result<NodeType, ParseError> {
+        let mut lhs = self.parse_nud()?;
+
+        while let Token::Op(op) = self.peek() {
+            let op = *op;
+            let bp = Self::binding_power(op);
+            if bp < min_bp {
+                break;
+            }
+            self.advance();
+            let rhs = self.parse_expression(bp + 1)?;
+            lhs = BinOp(op, lhs, rhs);
+        }
+
+        Ok(lhs)
+    }
+
+    /* The "null denotation" -- whatever an expression can start with:
+       a literal, a parenthesized expression, a unary +/-, or a name. */
+    fn parse_nud(&mut self) -> Result<NodeType, ParseError> {
+        match self.advance() {
+            Token::Integer(value) => Ok(Integer(value)),
+            Token::Float(value) => Ok(Float(value)),
+            Token::Name(name) => Ok(LoadLocation(NamedLocation(name))),
+            Token::LParen => {
+                let value = self.parse_expression(0)?;
+                self.expect(&Token::RParen)?;
+                Ok(value)
+            },
+            Token::Op(op @ (Operator::PLUS | Operator::MINUS)) => {
+                // Binds tighter than any binary operator, so `-2 * 3`
+                // parses as `(-2) * 3`, not `-(2 * 3)`.
+                let value = self.parse_expression(Self::binding_power(Operator::TIMES) + 1)?;
+                Ok(UnaryOp(op, value))
+            },
+            tok => Err(ParseError(format!("unexpected token in expression: {:?}", tok))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::to_source_string;
+
+    fn assert_round_trips(program: NodeType) {
+        let src = to_source_string(&program);
+        let reparsed = parse(&src).expect("parse failed");
+        assert_eq!(reparsed, program, "re-parsed source:\n{}", src);
+    }
+
+    #[test]
+    fn round_trip_model0() {
+        let program = crate::model0();
+        let src = to_source_string(&program);
+        let reparsed = parse_expr(&src).expect("parse_expr failed");
+        assert_eq!(reparsed, program, "re-parsed source:\n{}", src);
+    }
+
+    #[test]
+    fn round_trip_model1() {
+        assert_round_trips(crate::model1());
+    }
+
+    #[test]
+    fn round_trip_model2() {
+        assert_round_trips(crate::model2());
+    }
+
+    #[test]
+    fn round_trip_model3() {
+        assert_round_trips(crate::model3());
+    }
+
+    #[test]
+    fn round_trip_model4() {
+        assert_round_trips(crate::model4());
+    }
+}
@@ -0,0 +1,129 @@
+/* tokenizer.rs
+
+This module turns Wabbit source text into a flat stream of tokens
+for the parser to consume.  It's a plain hand-written scanner: walk
+the characters once, classify runs of digits/letters, and fall
+through to single- and double-character punctuation.
+*/
+
+use crate::model::Operator;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Integer(i32),
+    Float(f64),
+    Name(String),
+    Op(Operator),
+    Print,
+    Const,
+    Var,
+    If,
+    Else,
+    While,
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    Semi,
+    Assign,
+    Eof,
+}
+
+pub fn tokenize(src: &str) -> Vec<Token> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut pos = 0;
+    let mut tokens = Vec::new();
+
+    while pos < chars.len() {
+        let c = chars[pos];
+
+        if c.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let start = pos;
+            while pos < chars.len() && chars[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            if pos < chars.len() && chars[pos] == '.' {
+                pos += 1;
+                while pos < chars.len() && chars[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                let text: String = chars[start..pos].iter().collect();
+                tokens.push(Token::Float(text.parse().unwrap()));
+            } else {
+                let text: String = chars[start..pos].iter().collect();
+                tokens.push(Token::Integer(text.parse().unwrap()));
+            }
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = pos;
+            while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            let text: String = chars[start..pos].iter().collect();
+            tokens.push(match text.as_str() {
+                "print" => Token::Print,
+                "const" => Token::Const,
+                "var" => Token::Var,
+                "if" => Token::If,
+                "else" => Token::Else,
+                "while" => Token::While,
+                _ => Token::Name(text),
+            });
+            continue;
+        }
+
+        match c {
+            '+' => { tokens.push(Token::Op(Operator::PLUS)); pos += 1; },
+            '-' => { tokens.push(Token::Op(Operator::MINUS)); pos += 1; },
+            '*' => { tokens.push(Token::Op(Operator::TIMES)); pos += 1; },
+            '/' => { tokens.push(Token::Op(Operator::DIVIDE)); pos += 1; },
+            '{' => { tokens.push(Token::LBrace); pos += 1; },
+            '}' => { tokens.push(Token::RBrace); pos += 1; },
+            '(' => { tokens.push(Token::LParen); pos += 1; },
+            ')' => { tokens.push(Token::RParen); pos += 1; },
+            ';' => { tokens.push(Token::Semi); pos += 1; },
+            '<' => {
+                pos += 1;
+                if pos < chars.len() && chars[pos] == '=' {
+                    pos += 1;
+                    tokens.push(Token::Op(Operator::LE));
+                } else {
+                    tokens.push(Token::Op(Operator::LT));
+                }
+            },
+            '>' => {
+                pos += 1;
+                if pos < chars.len() && chars[pos] == '=' {
+                    pos += 1;
+                    tokens.push(Token::Op(Operator::GE));
+                } else {
+                    tokens.push(Token::Op(Operator::GT));
+                }
+            },
+            '=' => {
+                pos += 1;
+                if pos < chars.len() && chars[pos] == '=' {
+                    pos += 1;
+                    tokens.push(Token::Op(Operator::EQ));
+                } else {
+                    tokens.push(Token::Assign);
+                }
+            },
+            '!' if pos + 1 < chars.len() && chars[pos + 1] == '=' => {
+                tokens.push(Token::Op(Operator::NE));
+                pos += 2;
+            },
+            _ => panic!("tokenizer: unexpected character '{}' at position {}", c, pos),
+        }
+    }
+
+    tokens.push(Token::Eof);
+    tokens
+}
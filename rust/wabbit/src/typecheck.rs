@@ -0,0 +1,219 @@
+/* typecheck.rs
+
+A static type-checking pass over the `Node` tree.  It walks the
+program maintaining a symbol table of declared types, infers the
+type of every expression it meets along the way, and collects a
+`TypeError` for each mismatch instead of stopping at the first one --
+so `check` can report everything wrong with a program in one pass.
+*/
+
+use std::collections::HashMap;
+
+use crate::model::{location_name, Node};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Int,
+    Float,
+    Bool,
+}
+
+#[derive(Debug)]
+pub struct TypeError(pub String);
+
+pub fn check(program: &Node) -> Vec<TypeError> {
+    let mut checker = Checker { scope: HashMap::new(), errors: Vec::new() };
+    checker.check_stmt(program);
+    checker.errors
+}
+
+struct Checker {
+    scope: HashMap<String, Type>,
+    errors: Vec<TypeError>,
+}
+
+impl Checker {
+    fn error(&mut self, message: String) {
+        self.errors.push(TypeError(message));
+    }
+
+    fn dtype(&mut self, name: &str, dtype: &str) -> Option<Type> {
+        match dtype {
+            "int" => Some(Type::Int),
+            "float" => Some(Type::Float),
+            other => {
+                self.error(format!("'{}' has unknown type '{}'", name, other));
+                None
+            },
+        }
+    }
+
+    fn check_stmt(&mut self, node: &Node) {
+        use Node::*;
+        match node {
+            Nil => { },
+            Pair(first, rest) => {
+                self.check_stmt(first);
+                self.check_stmt(rest);
+            },
+            Statements(stmts) => {
+                for stmt in stmts {
+                    self.check_stmt(stmt);
+                }
+            },
+            ConstDefinition { name, dtype, value } | VarDefinition { name, dtype, value } => {
+                self.check_definition(name, dtype, value);
+            },
+            AssignmentStatement { location, expression } => {
+                let name = location_name(location).to_string();
+                let expr_ty = self.infer(expression);
+                match (self.scope.get(&name).copied(), expr_ty) {
+                    (Some(declared), Some(ty)) if declared != ty => {
+                        self.error(format!(
+                            "cannot assign {:?} to '{}', which is declared {:?}",
+                            ty, name, declared
+                        ));
+                    },
+                    (None, _) => {
+                        self.error(format!("assignment to undefined variable '{}'", name));
+                    },
+                    _ => { },
+                }
+            },
+            PrintStatement(expr) => {
+                self.infer(expr);
+            },
+            IfStatement { test, consequence, alternative } => {
+                self.check_test(test);
+                self.check_stmt(consequence);
+                self.check_stmt(alternative);
+            },
+            WhileStatement { test, body } => {
+                self.check_test(test);
+                self.check_stmt(body);
+            },
+            FunctionDefinition { name, .. } => {
+                self.error(format!("function '{}' is not supported by the type checker yet", name));
+            },
+            ReturnStatement(_) => {
+                self.error("'return' is not supported by the type checker yet".to_string());
+            },
+            _ => self.error(format!("not a statement: {:?}", node)),
+        }
+    }
+
+    /* `dtype` is the empty string when it was left off in source
+       (e.g. `const pi = 3.14159;` in model2), in which case it's
+       inferred from the initializer instead of being checked against it. */
+    fn check_definition(&mut self, name: &str, dtype: &str, value: &Node) {
+        let declared = if dtype.is_empty() { None } else { self.dtype(name, dtype) };
+        let inferred = match value {
+            Node::Nil => None,
+            value => self.infer(value),
+        };
+
+        let resolved = match (declared, inferred) {
+            (Some(d), Some(v)) if d == v => Some(d),
+            (Some(d), Some(v)) => {
+                self.error(format!(
+                    "'{}' declared as {:?} but initialized with {:?}",
+                    name, d, v
+                ));
+                Some(d)
+            },
+            (Some(d), None) => Some(d),
+            (None, Some(v)) => Some(v),
+            (None, None) => {
+                self.error(format!("cannot infer a type for '{}'", name));
+                None
+            },
+        };
+
+        if let Some(ty) = resolved {
+            self.scope.insert(name.to_string(), ty);
+        }
+    }
+
+    fn check_test(&mut self, node: &Node) {
+        match self.infer(node) {
+            Some(Type::Bool) => { },
+            Some(ty) => self.error(format!("expected a boolean test expression, got {:?}", ty)),
+            None => { },
+        }
+    }
+
+    fn infer(&mut self, node: &Node) -> Option<Type> {
+        use Node::*;
+        match node {
+            Integer(_) => Some(Type::Int),
+            Float(_) => Some(Type::Float),
+            BinOp { op, left, right } => {
+                let left_ty = self.infer(left);
+                let right_ty = self.infer(right);
+                match (left_ty, right_ty) {
+                    (Some(l), Some(r)) if l == r => {
+                        if op.is_comparison() { Some(Type::Bool) } else { Some(l) }
+                    },
+                    (Some(l), Some(r)) => {
+                        self.error(format!("type mismatch in binary operation: {:?} vs {:?}", l, r));
+                        None
+                    },
+                    _ => None,
+                }
+            },
+            UnaryOp { value, .. } => self.infer(value),
+            LoadLocation(loc) => {
+                let name = location_name(loc);
+                match self.scope.get(name) {
+                    Some(ty) => Some(*ty),
+                    None => {
+                        self.error(format!("undefined variable '{}'", name));
+                        None
+                    },
+                }
+            },
+            FunctionCall { name, .. } => {
+                self.error(format!("call to '{}' is not supported by the type checker yet", name));
+                None
+            },
+            _ => {
+                self.error(format!("not an expression: {:?}", node));
+                None
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::*;
+
+    #[test]
+    fn well_typed_program_has_no_errors() {
+        let program = Statements(vec![
+            VarDefinition("x".to_string(), "int".to_string(), Integer(2)),
+            PrintStatement(LoadLocation(NamedLocation("x".to_string()))),
+        ]);
+        assert!(check(&program).is_empty());
+    }
+
+    #[test]
+    fn assigning_a_mismatched_type_is_flagged() {
+        let program = Statements(vec![
+            VarDefinition("x".to_string(), "int".to_string(), Integer(2)),
+            AssignmentStatement(NamedLocation("x".to_string()), Float(1.0)),
+        ]);
+        let errors = check(&program);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].0.contains("cannot assign"), "{}", errors[0].0);
+    }
+
+    #[test]
+    fn using_an_undefined_variable_is_flagged() {
+        let program = PrintStatement(LoadLocation(NamedLocation("missing".to_string())));
+        let errors = check(&program);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].0.contains("undefined variable"), "{}", errors[0].0);
+    }
+}